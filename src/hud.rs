@@ -0,0 +1,95 @@
+use godot::classes::{Button, CanvasLayer, ICanvasLayer, Label, Timer};
+use godot::prelude::*;
+
+/// The heads-up display: score/health readouts, transient messages, and the
+/// start button whose press kicks off a new run.
+#[derive(GodotClass)]
+#[class(base=CanvasLayer)]
+pub struct Hud {
+    base: Base<CanvasLayer>,
+}
+
+#[godot_api]
+impl Hud {
+    // Emitted when the player presses Start. `Main::new_game` is wired to this
+    // in `Main::ready`.
+    #[signal]
+    pub fn start_game();
+
+    /// Flash a transient message; the MessageTimer hides it again.
+    #[func]
+    pub fn show_message(&self, text: GString) {
+        let mut message_label = self.base().get_node_as::<Label>("MessageLabel");
+        message_label.set_text(&text);
+        message_label.show();
+
+        let mut timer = self.base().get_node_as::<Timer>("MessageTimer");
+        timer.start();
+    }
+
+    // End the run: show the message, then bring the start button back after a
+    // 2-second scene-tree timer so the player can restart.
+    pub fn show_game_over(&self) {
+        self.show_message("Game Over".into());
+
+        let mut timer = self.base().get_tree().unwrap().create_timer(2.0).unwrap();
+        timer.connect("timeout", &self.base().callable("show_start_button"));
+    }
+
+    // Victory counterpart to `show_game_over`: same restart flow, a different
+    // message.
+    pub fn show_game_won(&self) {
+        self.show_message("You Win!".into());
+
+        let mut timer = self.base().get_tree().unwrap().create_timer(2.0).unwrap();
+        timer.connect("timeout", &self.base().callable("show_start_button"));
+    }
+
+    #[func]
+    fn show_start_button(&mut self) {
+        let mut message_label = self.base().get_node_as::<Label>("MessageLabel");
+        message_label.set_text("Kill the\nCreeps!");
+        message_label.show();
+
+        let mut button = self.base().get_node_as::<Button>("StartButton");
+        button.show();
+    }
+
+    pub fn update_score(&self, score: i64) {
+        let mut label = self.base().get_node_as::<Label>("ScoreLabel");
+        label.set_text(&score.to_string());
+    }
+
+    pub fn update_health(&self, health: i64) {
+        let mut label = self.base().get_node_as::<Label>("HealthLabel");
+        label.set_text(&health.to_string());
+    }
+
+    pub fn update_high_score(&self, high_score: i64) {
+        // HighScoreLabel is a newly added node; fetch it lazily so a HUD scene
+        // without it simply skips the readout instead of panicking.
+        if let Some(mut label) = self.base().try_get_node_as::<Label>("HighScoreLabel") {
+            label.set_text(&high_score.to_string());
+        }
+    }
+
+    #[func]
+    fn on_start_button_pressed(&mut self) {
+        let mut button = self.base().get_node_as::<Button>("StartButton");
+        button.hide();
+        self.signals().start_game().emit();
+    }
+
+    #[func]
+    fn on_message_timer_timeout(&self) {
+        let mut message_label = self.base().get_node_as::<Label>("MessageLabel");
+        message_label.hide();
+    }
+}
+
+#[godot_api]
+impl ICanvasLayer for Hud {
+    fn init(base: Base<CanvasLayer>) -> Self {
+        Self { base }
+    }
+}
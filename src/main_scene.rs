@@ -1,11 +1,43 @@
+use crate::player::{GameInput, MovementMode};
 use crate::{health, hud, mob, player};
 
-use godot::classes::{AudioStreamPlayer, Marker2D, PathFollow2D, RigidBody2D, Timer};
+use godot::classes::file_access::ModeFlags;
+use godot::classes::{AudioStreamPlayer, ConfigFile, Marker2D, PathFollow2D, RigidBody2D, Timer};
+use godot::global::Error;
 use godot::prelude::*;
 
-use rand::Rng as _;
+use rand::rngs::SmallRng;
+use rand::{Rng as _, SeedableRng as _};
 use std::f32::consts::PI;
 
+/// Fixed simulation step. All gameplay advances in whole 60 Hz ticks so that
+/// the player's motion and the spawn schedule are reproducible from a seed and
+/// an input stream (see `Main::advance_frame` for what is not).
+const FIXED_DT: f64 = 1.0 / 60.0;
+
+/// Number of local players the simulation is sized for. Kept at one today; the
+/// `[GameInput; NUM_PLAYERS]` shape leaves room for a second local player
+/// without reworking the step signature.
+const NUM_PLAYERS: usize = 1;
+
+/// Magic + version prefixing a replay file, so a stale format is rejected
+/// rather than silently mis-read.
+const REPLAY_MAGIC: [u8; 4] = *b"KTCR";
+const REPLAY_VERSION: u32 = 1;
+
+/// An in-progress replay playback: the recorded input words and how far we've
+/// re-fed them into `advance_frame`.
+struct Replay {
+    inputs: Vec<GameInput>,
+    cursor: usize,
+}
+
+/// Where best score and settings are persisted between sessions.
+const SAVE_PATH: &str = "user://savegame.cfg";
+
+/// Version stamped into the save file so older layouts can be migrated.
+const GAME_VERSION: &str = "1.0.0";
+
 // Deriving GodotClass makes the class available to Godot.
 #[derive(GodotClass)]
 #[class(base=Node)]
@@ -22,6 +54,54 @@ pub struct Main {
     next_health_kills: i64,
     #[export]
     starting_health: i64,
+    // Score needed to win. A non-positive value disables the win condition, so
+    // the game plays as an endless survival mode.
+    #[export]
+    target_score: i64,
+    // Progressive difficulty. `difficulty` ramps with survival time and drives
+    // the spawn interval, mob speed range, and simultaneous spawn count.
+    difficulty: f64,
+    base_spawn_interval: f64,
+    #[export]
+    difficulty_ramp: f64,
+    #[export]
+    min_spawn_interval: f64,
+    #[export]
+    max_simultaneous_spawns: i64,
+    // Explicit run seed. Leave at 0 to seed from the clock at `new_game`; set a
+    // non-zero value in the editor to reproduce a run exactly.
+    #[export]
+    seed: u64,
+    // The single simulation RNG. Every gameplay roll (spawn placement, mob
+    // angle/speed, health drops) goes through this so runs are reproducible.
+    rng: SmallRng,
+    // Seed actually used by the current run: the `seed` export when it is set,
+    // otherwise the clock-derived value. Kept separate from the export so a run
+    // with `seed == 0` re-derives a fresh seed every time instead of latching
+    // the first one, while still being readable to reproduce the run.
+    active_seed: u64,
+    // Fixed-step simulation clock: `frame` is the running tick count and
+    // `time_accum` carries the leftover `delta` between fixed steps.
+    frame: u64,
+    time_accum: f64,
+    // Frame-counted mob spawn schedule. Spawning is driven from `advance_frame`
+    // rather than the wall-clock `MobTimer` so a recorded input stream replays
+    // the same spawn frames. `spawning` gates it on (after the start delay, run
+    // live); `frames_until_spawn` counts down to the next wave.
+    spawning: bool,
+    frames_until_spawn: u64,
+    // Replay subsystem: `recording` appends each applied input word to
+    // `recorded_inputs`; `playback`, when set, feeds a recorded stream back into
+    // the simulation instead of sampling the keyboard.
+    recording: bool,
+    recorded_inputs: Vec<GameInput>,
+    playback: Option<Replay>,
+    // Latches once the run reaches a terminal state (game over or won) so a
+    // late event — e.g. an in-flight fireball killing a mob just after the win
+    // target is hit — cannot push the score further or replay the end sequence.
+    finished: bool,
+    // Best score across sessions, loaded from `user://savegame.cfg` at `ready`.
+    high_score: i64,
     base: Base<Node>,
 }
 
@@ -43,6 +123,24 @@ impl INode for Main {
             kill_count: 0,
             next_health_kills: 0,
             starting_health: 4,
+            target_score: 25,
+            difficulty: 0.0,
+            base_spawn_interval: 0.0,
+            difficulty_ramp: 0.05,
+            min_spawn_interval: 0.2,
+            max_simultaneous_spawns: 3,
+            seed: 0,
+            rng: SmallRng::seed_from_u64(0),
+            active_seed: 0,
+            frame: 0,
+            time_accum: 0.0,
+            spawning: false,
+            frames_until_spawn: 0,
+            recording: false,
+            recorded_inputs: Vec::new(),
+            playback: None,
+            finished: false,
+            high_score: 0,
             base,
         }
     }
@@ -65,11 +163,17 @@ impl INode for Main {
             .start_game()
             .connect_other(&main, Self::new_game);
 
-        // Connect Main.MobTimer::timeout -> Main::on_mob_timer_timeout.
-        self.mob_timer()
-            .signals()
-            .timeout()
-            .connect_other(&main, Self::on_mob_timer_timeout);
+        // Mob spawning is driven from `advance_frame` on a frame-counted
+        // schedule (see `step_spawns`), not the `MobTimer` timeout, so replays
+        // reproduce the spawn frames; the timer node survives only as the
+        // source of the designer-set base interval below.
+
+        // Remember the designer-set spawn interval as the easiest (slowest)
+        // tier; the difficulty curve interpolates from here down to the floor.
+        self.base_spawn_interval = self.mob_timer().get_wait_time();
+
+        // Restore the best score and persisted settings.
+        self.load_game();
 
         // Main.StartTimer::timeout -> Main::on_start_timer_timeout is set up in the Editor's Inspector UI, but could be done here as well,
         // as follows. Note that signal handlers connected via Rust do not need a #[func] annotation, they can remain entirely visible to Godot.
@@ -79,28 +183,118 @@ impl INode for Main {
         //     .timeout()
         //     .connect_other(&main, Self::on_start_timer_timeout);
     }
+
+    // Drive the simulation in fixed 60 Hz ticks. `delta` is accumulated so a
+    // varying frame rate still produces a deterministic, integer number of
+    // steps; each step samples the local input once and advances one frame.
+    fn physics_process(&mut self, delta: f64) {
+        self.time_accum += delta;
+        while self.time_accum >= FIXED_DT {
+            self.time_accum -= FIXED_DT;
+
+            // Ramp difficulty with survival time (only while the run is live).
+            if self.health > 0 && !self.finished {
+                self.difficulty += self.difficulty_ramp * FIXED_DT;
+            }
+
+            // During playback the input comes from the recorded stream; once it
+            // is exhausted, playback ends and live sampling resumes.
+            let input = match self.playback.as_mut() {
+                Some(replay) if replay.cursor < replay.inputs.len() => {
+                    let input = replay.inputs[replay.cursor];
+                    replay.cursor += 1;
+                    input
+                }
+                Some(_) => {
+                    self.playback = None;
+                    GameInput::sample_local()
+                }
+                None => GameInput::sample_local(),
+            };
+
+            if self.recording {
+                self.recorded_inputs.push(input);
+            }
+
+            self.advance_frame([input; NUM_PLAYERS]);
+        }
+    }
 }
 
 #[godot_api]
 impl Main {
     // No #[func] here, this method is directly called from Rust (via type-safe signals).
     fn game_over(&mut self) {
-        self.mob_timer().stop();
+        if self.finished {
+            return;
+        }
+        self.finished = true;
+        self.spawning = false;
 
         self.hud.bind_mut().show_game_over();
 
         self.music.stop();
         self.death_sound.play();
+
+        self.record_high_score();
+    }
+
+    // No #[func]. Mirrors `game_over`: the run ends victoriously, spawning stops
+    // and the HUD shows the win screen. Restart happens the same way, via the
+    // Hud's `start_game` signal wired to `new_game` in `ready`.
+    fn game_won(&mut self) {
+        if self.finished {
+            return;
+        }
+        self.finished = true;
+        self.spawning = false;
+
+        self.hud.bind_mut().show_game_won();
+
+        self.music.stop();
+        // Play the win jingle if the scene provides a WinSound node; fetch it
+        // lazily rather than via OnReady so a scene without the (newly added)
+        // node degrades to silence instead of panicking at `ready`.
+        if let Some(mut win_sound) = self
+            .base()
+            .try_get_node_as::<AudioStreamPlayer>("WinSound")
+        {
+            win_sound.play();
+        }
+
+        self.record_high_score();
     }
 
     // No #[func].
     pub fn new_game(&mut self) {
         let start_position = self.base().get_node_as::<Marker2D>("StartPosition");
 
+        // Seed the run: a non-zero `seed` export reproduces a run exactly,
+        // otherwise derive a fresh seed from the clock. The derived value lands
+        // in `active_seed`, never back in the export, so leaving `seed == 0`
+        // re-randomizes each run rather than repeating the first one.
+        self.active_seed = if self.seed != 0 {
+            self.seed
+        } else {
+            godot::classes::Time::singleton().get_ticks_usec()
+        };
+        self.rng = SmallRng::seed_from_u64(self.active_seed);
+
+        self.finished = false;
         self.score = 0;
         self.health = self.starting_health;
         self.kill_count = 0;
-        self.next_health_kills = rand::thread_rng().gen_range(6..=14);
+        self.next_health_kills = self.rng.gen_range(6..=14);
+
+        // Reset the fixed-step clock for the new run.
+        self.frame = 0;
+        self.time_accum = 0.0;
+
+        // Start each run at the easiest tier. Spawning stays off until the
+        // StartTimer elapses (the "Get Ready" delay).
+        self.difficulty = 0.0;
+        self.spawning = false;
+        self.frames_until_spawn = 0;
 
         self.player.bind_mut().start(start_position.get_position());
         self.start_timer().start();
@@ -113,9 +307,19 @@ impl Main {
         self.music.play();
     }
 
+    /// The seed driving the current run (the `seed` export when set, otherwise
+    /// the clock-derived value). Read it back to reproduce a clock-seeded run.
+    #[func]
+    fn get_active_seed(&self) -> u64 {
+        self.active_seed
+    }
+
     #[func] // needed because connected in Editor UI (see ready).
     fn on_start_timer_timeout(&mut self) {
-        self.mob_timer().start();
+        // Begin the frame-counted spawn schedule; the first wave goes out on the
+        // next simulation step.
+        self.spawning = true;
+        self.frames_until_spawn = 0;
     }
 
     pub fn on_player_hit(&mut self) {
@@ -136,19 +340,59 @@ impl Main {
 
     #[func]
     fn on_enemy_killed(&mut self) {
+        // Ignore kills credited by fireballs still in flight after the run has
+        // already ended, so the score can't creep past the win target.
+        if self.finished {
+            return;
+        }
+
         self.score += 1;
         self.hud.bind_mut().update_score(self.score);
 
+        if self.target_score > 0 && self.score >= self.target_score {
+            self.game_won();
+            return;
+        }
+
         self.kill_count += 1;
         if self.kill_count >= self.next_health_kills {
             self.spawn_health();
             self.kill_count = 0;
-            self.next_health_kills = rand::thread_rng().gen_range(6..=14);
+            self.next_health_kills = self.rng.gen_range(6..=14);
         }
     }
 
-    // No #[func], connected in pure Rust.
-    fn on_mob_timer_timeout(&mut self) {
+    // Frame-counted mob spawner, stepped once per `advance_frame`. Driving the
+    // schedule off the fixed-step frame count (rather than the wall-clock
+    // MobTimer) and the seeded RNG keeps the spawn sequence reproducible under
+    // replay.
+    fn step_spawns(&mut self) {
+        if !self.spawning || self.finished {
+            return;
+        }
+        if self.frames_until_spawn > 0 {
+            self.frames_until_spawn -= 1;
+            return;
+        }
+
+        // Spawn more mobs per wave at higher tiers, capped by the designer knob.
+        // Floor the knob at 1 so a 0-or-negative export can't invert the clamp
+        // bounds (which would panic) and still spawns at least one mob.
+        let max_spawns = self.max_simultaneous_spawns.max(1);
+        let count = (1 + (self.difficulty / 10.0) as i64).clamp(1, max_spawns);
+        for _ in 0..count {
+            self.spawn_mob();
+        }
+
+        // Tighten the interval toward the floor as difficulty climbs, converted
+        // from seconds to whole fixed-step frames.
+        let wait = (self.base_spawn_interval / (1.0 + self.difficulty)).max(self.min_spawn_interval);
+        self.frames_until_spawn = (wait / FIXED_DT).round() as u64;
+    }
+
+    // Spawn a single mob at a random point along the mob path, with a speed
+    // drawn from a range that widens with difficulty.
+    fn spawn_mob(&mut self) {
         let mut mob_spawn_location = self
             .base()
             .get_node_as::<PathFollow2D>("MobPath/MobSpawnLocation");
@@ -156,14 +400,13 @@ impl Main {
         // Instantiate the mob scene.
         let mut mob_scene = self.mob_scene.instantiate_as::<RigidBody2D>();
 
-        let mut rng = rand::thread_rng();
-        let progress = rng.gen_range(u32::MIN..u32::MAX);
+        let progress = self.rng.gen_range(u32::MIN..u32::MAX);
 
         mob_spawn_location.set_progress(progress as f32);
         mob_scene.set_position(mob_spawn_location.get_position());
 
         let mut direction = mob_spawn_location.get_rotation() + PI / 2.0;
-        direction += rng.gen_range(-PI / 4.0..PI / 4.0);
+        direction += self.rng.gen_range(-PI / 4.0..PI / 4.0);
 
         mob_scene.set_rotation(direction);
 
@@ -172,8 +415,14 @@ impl Main {
         let mut mob = mob_scene.cast::<mob::Mob>();
         let range = {
             // Local scope to bind `mob` user object
-            let mob = mob.bind();
-            rng.gen_range(mob.min_speed..mob.max_speed)
+            let (min_speed, max_speed) = {
+                let mob = mob.bind();
+                (mob.min_speed, mob.max_speed)
+            };
+            // Widen the upper end of the speed range with difficulty so late
+            // waves include faster mobs.
+            let max_speed = max_speed + real::from_f64(self.difficulty) * min_speed;
+            self.rng.gen_range(min_speed..max_speed)
         };
 
         mob.set_linear_velocity(Vector2::new(range, 0.0).rotated(real::from_f32(direction)));
@@ -190,13 +439,12 @@ impl Main {
 
     fn spawn_health(&mut self) {
         let mut health_pickup = self.health_scene.instantiate_as::<health::Health>();
-        let mut rng = rand::thread_rng();
         let screen_size = self.player.bind().get_screen_size();
-        let x = rng.gen_range(0.0..screen_size.x);
-        let y = rng.gen_range(0.0..screen_size.y);
+        let x = self.rng.gen_range(0.0..screen_size.x);
+        let y = self.rng.gen_range(0.0..screen_size.y);
         health_pickup.set_global_position(Vector2::new(x, y));
 
-        let amount = if rng.gen_bool(0.1) { 3 } else { 1 };
+        let amount = if self.rng.gen_bool(0.1) { 3 } else { 1 };
         health_pickup.bind_mut().set_heal_amount(amount);
 
         let main = self.to_gd();
@@ -209,4 +457,211 @@ impl Main {
         self.health = (self.health + amount).min(self.starting_health);
         self.hud.bind_mut().update_health(self.health);
     }
+
+    /// Advance the whole simulation by one fixed step from the given inputs.
+    ///
+    /// This is the deterministic, input-driven core of the run: it steps the
+    /// player from the injected `inputs` and runs the frame-counted, seeded mob
+    /// spawner, touching neither `Input::singleton`, a wall-clock, nor un-seeded
+    /// RNG. The same seed plus the same input stream therefore reproduces the
+    /// spawn schedule and the player's motion, which is what the replay
+    /// subsystem relies on.
+    ///
+    /// This is deliberately *not* GGRS-style rollback netcode. The spawned mobs
+    /// are `RigidBody2D`s integrated by the Godot physics engine and fireballs
+    /// run their own `process`, so their exact motion is not snapshotted or
+    /// restorable here; rolling back and re-simulating those engine-driven
+    /// bodies is out of scope for this simulation core.
+    fn advance_frame(&mut self, inputs: [GameInput; NUM_PLAYERS]) {
+        self.player
+            .bind_mut()
+            .advance(inputs[0], real::from_f64(FIXED_DT));
+        self.step_spawns();
+        self.frame += 1;
+    }
+
+    /// Begin recording the per-frame input stream of the current run. The seed
+    /// is captured on [`Main::stop_recording`] so the recorded seed + input
+    /// stream can be re-fed on playback. Once spawning has begun, spawn timing
+    /// is frame-counted and seeded (see `step_spawns`), so playback reproduces
+    /// the same spawn intervals and placements. Spawning itself begins when the
+    /// StartTimer (a scene timer, not a frame count) fires, so the frame of the
+    /// first wave is not fixed by the input stream. The spawned RigidBody2D mobs
+    /// are then stepped by the physics engine as usual.
+    #[func]
+    fn start_recording(&mut self) {
+        self.recording = true;
+        self.recorded_inputs.clear();
+    }
+
+    /// Stop recording and flush the seed header + input words to a binary log
+    /// under `user://replays/`. Returns the written path (empty on failure).
+    #[func]
+    fn stop_recording(&mut self) -> GString {
+        if !self.recording {
+            return GString::new();
+        }
+        self.recording = false;
+        self.save_replay()
+    }
+
+    /// Load a replay file and start playing it back: restore its seed, restart
+    /// the run from it, and feed the recorded inputs into `advance_frame` until
+    /// the stream is exhausted. See [`Main::start_recording`] for what playback
+    /// does and does not reproduce.
+    #[func]
+    fn load_replay(&mut self, path: GString) {
+        let Some(file) = godot::classes::FileAccess::open(&path, ModeFlags::READ) else {
+            godot_error!("replay: cannot open {path}");
+            return;
+        };
+
+        let bytes = file.get_buffer(file.get_length() as i64);
+        let bytes = bytes.as_slice();
+
+        let header_len = REPLAY_MAGIC.len() + std::mem::size_of::<u32>() + std::mem::size_of::<u64>();
+        if bytes.len() < header_len || bytes[..4] != REPLAY_MAGIC {
+            godot_error!("replay: {path} is not a valid replay file");
+            return;
+        }
+
+        let version = u32::from_le_bytes(bytes[4..8].try_into().unwrap());
+        if version != REPLAY_VERSION {
+            godot_error!("replay: unsupported version {version}");
+            return;
+        }
+
+        // The payload is a flat array of `GameInput` words; a truncated or
+        // corrupt file whose length does not divide evenly would panic in
+        // `cast_slice`, so reject it up front.
+        let payload = &bytes[header_len..];
+        if payload.len() % std::mem::size_of::<GameInput>() != 0 {
+            godot_error!("replay: {path} has a truncated input stream");
+            return;
+        }
+
+        // Pin the recorded seed into the export so `new_game` replays the run
+        // from exactly that seed rather than deriving a fresh one.
+        self.seed = u64::from_le_bytes(bytes[8..16].try_into().unwrap());
+        let inputs: Vec<GameInput> = bytemuck::cast_slice(payload).to_vec();
+
+        self.new_game();
+        self.playback = Some(Replay { inputs, cursor: 0 });
+    }
+
+    /// Serialize the recorded stream to `user://replays/` and return its path.
+    fn save_replay(&self) -> GString {
+        const DIR: &str = "user://replays";
+        godot::classes::DirAccess::make_dir_recursive_absolute(DIR);
+
+        let path = GString::from(format!("{DIR}/replay_{}.rpl", self.frame));
+        let Some(mut file) = godot::classes::FileAccess::open(&path, ModeFlags::WRITE) else {
+            godot_error!("replay: cannot write {path}");
+            return GString::new();
+        };
+
+        let mut buf =
+            Vec::with_capacity(16 + self.recorded_inputs.len() * std::mem::size_of::<GameInput>());
+        buf.extend_from_slice(&REPLAY_MAGIC);
+        buf.extend_from_slice(&REPLAY_VERSION.to_le_bytes());
+        buf.extend_from_slice(&self.active_seed.to_le_bytes());
+        buf.extend_from_slice(bytemuck::cast_slice(&self.recorded_inputs));
+
+        file.store_buffer(&PackedByteArray::from(buf.as_slice()));
+        path
+    }
+
+    // Promote the current score to the high score if it beats the stored best,
+    // persist it, and push it to the HUD.
+    fn record_high_score(&mut self) {
+        if self.score > self.high_score {
+            self.high_score = self.score;
+            self.save_game();
+        }
+        self.hud.bind_mut().update_high_score(self.high_score);
+    }
+
+    /// Load the best score and settings from `user://savegame.cfg`. Missing
+    /// files leave the editor defaults in place; an older `game_version` is
+    /// migrated by rewriting the file in the current layout.
+    #[func]
+    fn load_game(&mut self) {
+        let mut cfg = ConfigFile::new_gd();
+        if cfg.load(SAVE_PATH) != Error::OK {
+            return;
+        }
+
+        self.high_score = cfg
+            .get_value_ex("progress", "high_score")
+            .default(&0i64.to_variant())
+            .done()
+            .to();
+        self.starting_health = cfg
+            .get_value_ex("settings", "starting_health")
+            .default(&self.starting_health.to_variant())
+            .done()
+            .to();
+
+        let invincibility_time: f64 = cfg
+            .get_value_ex("settings", "invincibility_time")
+            .default(&self.player.bind().get_invincibility_time().to_variant())
+            .done()
+            .to();
+        let movement_mode: MovementMode = cfg
+            .get_value_ex("settings", "movement_mode")
+            .default(&MovementMode::Direct.to_variant())
+            .done()
+            .to();
+        {
+            let mut player = self.player.bind_mut();
+            player.set_invincibility_time(invincibility_time);
+            player.set_movement_mode(movement_mode);
+        }
+
+        let version: GString = cfg
+            .get_value_ex("meta", "game_version")
+            .default(&GString::new().to_variant())
+            .done()
+            .to();
+        if version != GString::from(GAME_VERSION) {
+            // Upgrade the on-disk layout to the current version.
+            self.save_game();
+        }
+    }
+
+    /// Persist the best score and settings to `user://savegame.cfg`.
+    #[func]
+    fn save_game(&self) {
+        let mut cfg = ConfigFile::new_gd();
+        cfg.set_value(
+            "meta",
+            "game_version",
+            &GString::from(GAME_VERSION).to_variant(),
+        );
+        cfg.set_value("progress", "high_score", &self.high_score.to_variant());
+        cfg.set_value(
+            "settings",
+            "starting_health",
+            &self.starting_health.to_variant(),
+        );
+        cfg.set_value(
+            "settings",
+            "invincibility_time",
+            &self.player.bind().get_invincibility_time().to_variant(),
+        );
+        cfg.set_value(
+            "settings",
+            "movement_mode",
+            &self.player.bind().get_movement_mode().to_variant(),
+        );
+        cfg.save(SAVE_PATH);
+    }
+
+    /// Clear the stored best score, persist the reset, and refresh the HUD.
+    #[func]
+    fn reset_progress(&mut self) {
+        self.high_score = 0;
+        self.save_game();
+        self.hud.bind_mut().update_high_score(self.high_score);
+    }
 }
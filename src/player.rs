@@ -2,6 +2,87 @@ use crate::fireball;
 use godot::classes::{AnimatedSprite2D, Area2D, CollisionShape2D, IArea2D, Input, PackedScene};
 use godot::prelude::*;
 
+use bytemuck::{Pod, Zeroable};
+
+/// Packed per-frame input for a single player.
+///
+/// Movement buttons live in the low bits of `buttons` and the facing is kept as
+/// `i8` signs, so the whole struct is `Pod`: it can be memcpy'd straight into a
+/// rollback ring buffer, a replay log word, or a netcode packet without any
+/// per-field serialization. Sampling the live keyboard state is confined to
+/// [`GameInput::sample_local`]; the simulation only ever consumes an already
+/// captured value, never `Input::singleton` directly.
+#[repr(C)]
+#[derive(Clone, Copy, Default, PartialEq, Eq, Pod, Zeroable)]
+pub struct GameInput {
+    pub buttons: u8,
+    pub last_horizontal: i8,
+    pub last_vertical: i8,
+    _pad: u8,
+}
+
+impl GameInput {
+    pub const MOVE_RIGHT: u8 = 1 << 0;
+    pub const MOVE_LEFT: u8 = 1 << 1;
+    pub const MOVE_DOWN: u8 = 1 << 2;
+    pub const MOVE_UP: u8 = 1 << 3;
+    pub const SHOOT: u8 = 1 << 4;
+
+    pub fn pressed(self, flag: u8) -> bool {
+        self.buttons & flag != 0
+    }
+
+    /// Poll the live Godot input state into a `GameInput`. This is the *only*
+    /// place that touches `Input::singleton`; it is called once per fixed step
+    /// to capture the local player's intent, never from within the simulation.
+    pub fn sample_local() -> Self {
+        let input = Input::singleton();
+        let mut buttons = 0u8;
+        let mut last_horizontal = 0i8;
+        let mut last_vertical = 0i8;
+
+        if input.is_action_pressed("move_right") {
+            buttons |= Self::MOVE_RIGHT;
+            last_horizontal = 1;
+        }
+        if input.is_action_pressed("move_left") {
+            buttons |= Self::MOVE_LEFT;
+            last_horizontal = -1;
+        }
+        if input.is_action_pressed("move_down") {
+            buttons |= Self::MOVE_DOWN;
+            last_vertical = 1;
+        }
+        if input.is_action_pressed("move_up") {
+            buttons |= Self::MOVE_UP;
+            last_vertical = -1;
+        }
+        if input.is_action_pressed("shoot") {
+            buttons |= Self::SHOOT;
+        }
+
+        Self {
+            buttons,
+            last_horizontal,
+            last_vertical,
+            _pad: 0,
+        }
+    }
+}
+
+/// How the player ship responds to movement input.
+///
+/// `Direct` is the classic instantaneous velocity; `Inertial` gives an
+/// Asteroids-style thrust-and-drift feel that steers the `direction` facing
+/// vector and derives from it the facing signs the fireball launch keys off.
+#[derive(GodotConvert, Var, Export, Default, Clone, Copy, PartialEq, Eq)]
+#[godot(via = i64)]
+pub enum MovementMode {
+    #[default]
+    Direct,
+    Inertial,
+}
+
 #[derive(GodotClass)]
 #[class(base=Area2D)]
 pub struct Player {
@@ -10,10 +91,26 @@ pub struct Player {
     fireball_scene: OnReady<Gd<PackedScene>>,
     #[export]
     invincibility_time: f64,
+    #[export]
+    movement_mode: MovementMode,
+    // Tuning for `Inertial` mode.
+    #[export]
+    thrust: real,
+    #[export]
+    turn_speed: real,
+    #[export]
+    max_speed: real,
+    #[export]
+    drag: real,
+    // Persistent velocity carried between frames in `Inertial` mode.
+    velocity: Vector2,
     direction: Vector2,
     last_horizontal: real,
     last_vertical: real,
     hit: bool,
+    // Edge detection for the shoot button: the simulation only sees a button
+    // *state* each frame, so we latch it to reproduce the old `just_pressed`.
+    shoot_latched: bool,
 
     base: Base<Area2D>,
 }
@@ -112,58 +209,71 @@ impl Player {
     pub fn get_screen_size(&self) -> Vector2 {
         self.screen_size
     }
-}
-
-#[godot_api]
-impl IArea2D for Player {
-    fn init(base: Base<Area2D>) -> Self {
-        Player {
-            speed: 400.0,
-            screen_size: Vector2::new(0.0, 0.0),
-            fireball_scene: OnReady::from_loaded("res://Fireball.tscn"),
-            invincibility_time: 0.5,
-            direction: Vector2::UP,
-            last_horizontal: 1.0,
-            last_vertical: -1.0,
-            hit: false,
-            base,
-        }
-    }
 
-    fn ready(&mut self) {
-        let viewport = self.base().get_viewport_rect();
-        self.screen_size = viewport.size;
-        self.base_mut().hide();
-
-        // Signal setup
-        self.signals()
-            .body_entered()
-            .connect_self(Self::on_player_body_entered);
-    }
-
-    // `delta` can be f32 or f64; #[godot_api] macro converts transparently.
-    fn process(&mut self, delta: f32) {
+    /// Advance the player by one fixed step from an injected `GameInput`.
+    ///
+    /// This is the deterministic movement core: it never reads `Input` or a
+    /// wall-clock, so `Main::advance_frame` can drive or replay it from a
+    /// recorded input stream. The previous engine `process` callback has been
+    /// folded into here.
+    pub fn advance(&mut self, input: GameInput, delta: real) {
         let mut animated_sprite = self
             .base()
             .get_node_as::<AnimatedSprite2D>("AnimatedSprite2D");
 
+        let velocity = match self.movement_mode {
+            MovementMode::Direct => self.advance_direct(input, &mut animated_sprite),
+            MovementMode::Inertial => self.advance_inertial(input, &mut animated_sprite, delta),
+        };
+
+        let change = velocity * delta;
+        let position = self.base().get_global_position() + change;
+        let position = Vector2::new(
+            position.x.clamp(0.0, self.screen_size.x),
+            position.y.clamp(0.0, self.screen_size.y),
+        );
+        self.base_mut().set_global_position(position);
+
+        let shoot = input.pressed(GameInput::SHOOT);
+        if shoot && !self.shoot_latched {
+            let mut parent_node = self.base().get_parent().unwrap();
+            let mut fireball = self.fireball_scene.instantiate_as::<fireball::Fireball>();
+            let direction = if self.last_vertical > 0.0 {
+                Vector2::DOWN
+            } else {
+                Vector2::UP
+            };
+            let angular_speed = 10.0 * self.last_horizontal * -self.last_vertical;
+            fireball.bind_mut().launch(direction, 600.0, angular_speed);
+            fireball.set_global_position(self.base().get_global_position());
+            fireball.connect("enemy_killed", &parent_node.callable("on_enemy_killed"));
+            parent_node.add_child(&fireball);
+        }
+        self.shoot_latched = shoot;
+    }
+
+    // Classic instantaneous movement: velocity is recomputed from scratch each
+    // frame and points straight along the pressed directions.
+    fn advance_direct(
+        &mut self,
+        input: GameInput,
+        animated_sprite: &mut Gd<AnimatedSprite2D>,
+    ) -> Vector2 {
         let mut velocity = Vector2::new(0.0, 0.0);
 
-        // Note: exact=false by default, in Rust we have to provide it explicitly
-        let input = Input::singleton();
-        if input.is_action_pressed("move_right") {
+        if input.pressed(GameInput::MOVE_RIGHT) {
             velocity += Vector2::RIGHT;
             self.last_horizontal = 1.0;
         }
-        if input.is_action_pressed("move_left") {
+        if input.pressed(GameInput::MOVE_LEFT) {
             velocity += Vector2::LEFT;
             self.last_horizontal = -1.0;
         }
-        if input.is_action_pressed("move_down") {
+        if input.pressed(GameInput::MOVE_DOWN) {
             velocity += Vector2::DOWN;
             self.last_vertical = 1.0;
         }
-        if input.is_action_pressed("move_up") {
+        if input.pressed(GameInput::MOVE_UP) {
             velocity += Vector2::UP;
             self.last_vertical = -1.0;
         }
@@ -190,28 +300,99 @@ impl IArea2D for Player {
             animated_sprite.stop();
         }
 
-        let change = velocity * delta;
-        let position = self.base().get_global_position() + change;
-        let position = Vector2::new(
-            position.x.clamp(0.0, self.screen_size.x),
-            position.y.clamp(0.0, self.screen_size.y),
-        );
-        self.base_mut().set_global_position(position);
+        velocity
+    }
 
-        let input = Input::singleton();
-        if input.is_action_just_pressed("shoot") {
-            let mut parent_node = self.base().get_parent().unwrap();
-            let mut fireball = self.fireball_scene.instantiate_as::<fireball::Fireball>();
-            let direction = if self.last_vertical > 0.0 {
-                Vector2::DOWN
+    // Asteroids-style thrust-and-drift: left/right rotate the facing, up/down
+    // thrust along it, and the persistent `velocity` coasts under per-frame
+    // drag, clamped to `max_speed`.
+    fn advance_inertial(
+        &mut self,
+        input: GameInput,
+        animated_sprite: &mut Gd<AnimatedSprite2D>,
+        delta: real,
+    ) -> Vector2 {
+        if input.pressed(GameInput::MOVE_LEFT) {
+            self.direction = self.direction.rotated(-self.turn_speed * delta);
+        }
+        if input.pressed(GameInput::MOVE_RIGHT) {
+            self.direction = self.direction.rotated(self.turn_speed * delta);
+        }
+
+        let mut thrusting = false;
+        if input.pressed(GameInput::MOVE_UP) {
+            self.velocity += self.direction * self.thrust * delta;
+            thrusting = true;
+        }
+        if input.pressed(GameInput::MOVE_DOWN) {
+            self.velocity -= self.direction * self.thrust * delta;
+            thrusting = true;
+        }
+
+        // Coast: damp the velocity each frame and cap the top speed.
+        self.velocity *= self.drag;
+        if self.velocity.length() > self.max_speed {
+            self.velocity = self.velocity.normalized() * self.max_speed;
+        }
+
+        // Keep the facing signs in sync with the heading: fireballs launch up or
+        // down by its vertical sign and curve toward its horizontal sign.
+        self.last_horizontal = if self.direction.x >= 0.0 { 1.0 } else { -1.0 };
+        self.last_vertical = if self.direction.y >= 0.0 { 1.0 } else { -1.0 };
+
+        if thrusting {
+            let animation = if self.direction.x.abs() > self.direction.y.abs() {
+                animated_sprite.set_flip_v(false);
+                animated_sprite.set_flip_h(self.direction.x < 0.0);
+                "right"
             } else {
-                Vector2::UP
+                animated_sprite.set_flip_v(self.direction.y > 0.0);
+                "up"
             };
-            let angular_speed = 10.0 * self.last_horizontal * -self.last_vertical;
-            fireball.bind_mut().launch(direction, 600.0, angular_speed);
-            fireball.set_global_position(self.base().get_global_position());
-            fireball.connect("enemy_killed", &parent_node.callable("on_enemy_killed"));
-            parent_node.add_child(&fireball);
+            animated_sprite.play_ex().name(animation).done();
+        } else {
+            animated_sprite.stop();
         }
+
+        self.velocity
     }
 }
+
+#[godot_api]
+impl IArea2D for Player {
+    fn init(base: Base<Area2D>) -> Self {
+        Player {
+            speed: 400.0,
+            screen_size: Vector2::new(0.0, 0.0),
+            fireball_scene: OnReady::from_loaded("res://Fireball.tscn"),
+            invincibility_time: 0.5,
+            movement_mode: MovementMode::Direct,
+            thrust: 600.0,
+            turn_speed: 4.0,
+            max_speed: 400.0,
+            drag: 0.98,
+            velocity: Vector2::ZERO,
+            direction: Vector2::UP,
+            last_horizontal: 1.0,
+            last_vertical: -1.0,
+            hit: false,
+            shoot_latched: false,
+            base,
+        }
+    }
+
+    fn ready(&mut self) {
+        let viewport = self.base().get_viewport_rect();
+        self.screen_size = viewport.size;
+        self.base_mut().hide();
+
+        // Signal setup
+        self.signals()
+            .body_entered()
+            .connect_self(Self::on_player_body_entered);
+    }
+
+    // Movement is no longer driven by the per-frame engine `process` callback:
+    // `Main::advance_frame` steps the player at a fixed 60 Hz via
+    // `Player::advance`, so the simulation stays deterministic and replayable.
+}